@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+/// generate a CHANGELOG.md from conventional commit messages
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+pub struct Args {
+    /// path to a Tera template for the document header, rendered once
+    #[arg(long)]
+    pub template_header: Option<PathBuf>,
+    /// path to a Tera template for the release list, rendered once with all releases in scope
+    #[arg(long)]
+    pub template_body: Option<PathBuf>,
+    /// path to a Tera template for the document footer, rendered once
+    #[arg(long)]
+    pub template_footer: Option<PathBuf>,
+
+    /// compute the next semver from the Unreleased commits and use it in place of "Unreleased"
+    #[arg(long)]
+    pub bump: bool,
+
+    /// print only the computed version to stdout instead of writing CHANGELOG.md (implies --bump)
+    #[arg(long)]
+    pub print_version: bool,
+
+    /// only generate releases newer than the newest one already in CHANGELOG.md, and splice
+    /// them in above it instead of regenerating the whole file
+    #[arg(long)]
+    pub prepend: bool,
+
+    /// print the fully-parsed release data as JSON instead of rendering CHANGELOG.md
+    #[arg(long)]
+    pub context: bool,
+
+    /// render from a JSON document previously captured with --context, instead of reading git
+    #[arg(long)]
+    pub from_context: Option<PathBuf>,
+}
+
+impl Args {
+    // true when the user asked for templated rendering instead of the default builder
+    pub fn any_template(&self) -> bool {
+        self.template_header.is_some() || self.template_body.is_some() || self.template_footer.is_some()
+    }
+
+    // true when the next version should be computed, either to substitute into the
+    // header or to print on its own
+    pub fn should_bump(&self) -> bool {
+        self.bump || self.print_version
+    }
+}
@@ -0,0 +1,100 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use serde::Deserialize;
+
+// name of the optional configuration file, searched for at the repo root
+const CONFIG_FILE: &str = ".git-changelog.toml";
+
+// a single commit-type -> section-title mapping entry
+#[derive(Debug, Deserialize)]
+struct Scope {
+    r#type: String,
+    title: String,
+}
+
+// on-disk shape of `.git-changelog.toml`
+#[derive(Debug, Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    scopes: Vec<Scope>,
+    #[serde(default)]
+    ignore: Vec<String>,
+    // overrides host-based provider detection, e.g. "github", "gitlab", "bitbucket"
+    #[serde(default)]
+    provider: Option<String>,
+}
+
+// resolved configuration used throughout the rest of the program
+#[derive(Debug)]
+pub struct Config {
+    // ordered commit-type -> section-title mapping, in output order
+    pub scopes: Vec<(String, String)>,
+    // commit types to drop, even if also present in `scopes`; breaking commits of
+    // these types are still listed under "Breaking Changes"
+    pub ignore: Vec<String>,
+    // overrides host-based provider detection, e.g. "github", "gitlab", "bitbucket"
+    pub provider: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            scopes: vec![
+                ("feat".to_string(), "Added".to_string()),
+                ("refactor".to_string(), "Changed".to_string()),
+                ("fix".to_string(), "Fixed".to_string()),
+            ],
+            ignore: Vec::new(),
+            provider: None,
+        }
+    }
+}
+
+impl From<RawConfig> for Config {
+    fn from(raw: RawConfig) -> Self {
+        // an absent `[[scopes]]` table (e.g. a config that only sets `ignore`) should
+        // fall back to the built-in scopes rather than leave every section empty
+        let scopes = if raw.scopes.is_empty() {
+            Config::default().scopes
+        } else {
+            raw.scopes.into_iter().map(|s| (s.r#type, s.title)).collect()
+        };
+        Config {
+            scopes,
+            ignore: raw.ignore,
+            provider: raw.provider,
+        }
+    }
+}
+
+// find the root of the current repo, used to locate the config file
+fn repo_root() -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let path = std::str::from_utf8(&output.stdout).ok()?.trim();
+    Some(PathBuf::from(path))
+}
+
+impl Config {
+    // load `.git-changelog.toml` from the repo root, falling back to the
+    // built-in defaults when no file is present
+    pub fn load() -> Config {
+        let path = repo_root()
+            .unwrap_or_default()
+            .join(CONFIG_FILE);
+
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Config::default();
+        };
+
+        let raw: RawConfig =
+            toml::from_str(&text).unwrap_or_else(|e| panic!("invalid {}: {}", CONFIG_FILE, e));
+
+        raw.into()
+    }
+}
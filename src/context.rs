@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::CommitList;
+
+// the fully-parsed git-extraction stage, serialized so rendering can be decoupled
+// from `git_log`/`git_remote_url` and run in an environment without git history
+#[derive(Serialize, Deserialize)]
+pub struct Context {
+    pub remote_url: String,
+    // the resolved provider name (e.g. "github"), captured so a provider override
+    // reproduces identical output wherever the context is later rendered
+    pub provider: String,
+    pub chunks: IndexMap<String, (String, HashMap<String, CommitList>)>,
+}
@@ -1,28 +1,54 @@
-use std::{collections::HashMap, fs::File, io::Write, process::Command};
+mod cli;
+mod config;
+mod context;
+mod remote;
+mod template;
+mod version;
 
+use std::{collections::HashMap, fs, fs::File, io::Write, process::Command};
+
+use clap::Parser;
 use indexmap::IndexMap;
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
 
-// regex to match git log output
+use cli::Args;
+use config::Config;
+use remote::Remote;
+
+// field separator between %cs/%d/%s/%h/%b in a git_log record
+const FIELD_SEP: char = '\u{1f}';
+// record separator between commits in a git_log record
+const RECORD_SEP: char = '\u{1e}';
+
+// reserved chunk key for breaking changes, printed ahead of the configured scopes
+pub(crate) const BREAKING_SCOPE: &str = "breaking-change";
+
+// a single parsed commit: conventional-commit scope, subject and abbreviated hash
+pub(crate) type CommitList = Vec<(Option<String>, String, String)>;
+
+// regex to match conventional-commit subjects: `type(scope)!: subject`
 static RGX_GIT: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(
-        r"(?P<date>\d{4}-\d{2}-\d{2})  (\((?P<refs>.*)\) )?((?P<scope>\w+): )?(?P<commit>.*)",
-    )
-    .unwrap()
+    Regex::new(r"^(?P<type>\w+)(\((?P<scope>[\w./-]+)\))?(?P<breaking>!)?: (?P<commit>.*)$").unwrap()
 });
 
+// regex to match a `BREAKING CHANGE:` footer in a commit body
+static RGX_BREAKING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^BREAKING CHANGE: .*$").unwrap());
+
 // regex to match git refs
 static RGX_REF: Lazy<Regex> = Lazy::new(|| Regex::new(r"tag: (?P<version>[v0-9.]+)").unwrap());
 
-// valid scopes and corresponding changelog section title
-static VALID_SCOPES: Lazy<Vec<(&str, &str)>> =
-    Lazy::new(|| vec![("feat", "Added"), ("refactor", "Changed"), ("fix", "Fixed")]);
+// regex to match a release header already written to CHANGELOG.md
+static RGX_VERSION_HEADER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^## \[(?P<version>[^\]]+)\]").unwrap());
 
-// extract git log output as lines
+// extract git log output as records of date, refs, subject, abbreviated hash and body
 fn git_log() -> Vec<String> {
     let output = Command::new("git")
-        .args(["log", "--pretty=%cs %d %s"])
+        .args([
+            "log",
+            &format!("--pretty=%cs{FIELD_SEP}%d{FIELD_SEP}%s{FIELD_SEP}%h{FIELD_SEP}%b{RECORD_SEP}"),
+        ])
         .output()
         .expect("`git` must be installed");
 
@@ -33,8 +59,9 @@ fn git_log() -> Vec<String> {
 
     std::str::from_utf8(&output.stdout)
         .unwrap()
-        .split('\n')
-        .map(|s| s.to_string())
+        .split(RECORD_SEP)
+        .map(|s| s.trim_start_matches('\n').to_string())
+        .filter(|s| !s.is_empty())
         .collect::<Vec<String>>()
 }
 
@@ -64,44 +91,61 @@ fn get_match(caps: &Option<Captures>, kind: &str) -> Option<String> {
 }
 
 // create changelog header for each version
-fn get_header(version0: Option<String>, version: &str, url: &str, date: &str) -> Vec<String> {
-    let header;
+fn get_header(version0: Option<String>, version: &str, remote: &Remote, date: &str) -> Vec<String> {
     let version_text = match version {
         "main" => "Unreleased",
         _ => version,
     };
-    if let Some(ref version_prev) = version0 {
-        header = format!(
-            "## [{}]({}/compare/{}...{}) - {}",
-            version_text, url, version_prev, version, date
-        );
-    } else {
-        header = format!(
-            "## [{}]({}/releases/tag/{}) - {}",
-            version_text, url, version, date
-        );
-    }
+    let url = match &version0 {
+        Some(version_prev) => remote.compare_url(version_prev, version),
+        None => remote.release_url(version),
+    };
+    let header = format!("## [{}]({}) - {}", version_text, url, date);
     vec![header, "".to_string()]
 }
 
-// capitalize first letter and format bulletpoint
-fn get_list_bullet(s: &str) -> String {
+// capitalize the first letter of a commit subject
+pub(crate) fn capitalize(s: &str) -> String {
     let mut c = s.chars();
-    let bullet = match c.next() {
+    match c.next() {
         None => String::new(),
         Some(ch) => ch.to_uppercase().collect::<String>() + c.as_str(),
+    }
+}
+
+// render a commit subject as the text of a bulletpoint (without the `* ` marker):
+// autolink issue refs, bold-prefix the scope, and append a short linked commit hash
+pub(crate) fn format_item(remote: &Remote, scope: Option<&str>, hash: &str, s: &str) -> String {
+    let subject = remote.autolink(&capitalize(s));
+    let bullet = match scope {
+        Some(scope) => format!("**{}:** {}", scope, subject),
+        None => subject,
     };
-    format!("* {}", bullet)
+    if hash.is_empty() {
+        bullet
+    } else {
+        format!("{} ({})", bullet, remote.commit_link(hash))
+    }
+}
+
+// capitalize first letter and format bulletpoint
+fn get_list_bullet(remote: &Remote, scope: Option<&str>, hash: &str, s: &str) -> String {
+    format!("* {}", format_item(remote, scope, hash, s))
 }
 
 // create specific changelog chunk for each version
-fn get_chunk(chunk0: &HashMap<String, Vec<String>>, scope: &str, header: &str) -> Vec<String> {
+fn get_chunk(
+    chunk0: &HashMap<String, CommitList>,
+    scope: &str,
+    header: &str,
+    remote: &Remote,
+) -> Vec<String> {
     let mut chunk = Vec::new();
     if let Some(items) = chunk0.get(scope) {
         if !items.is_empty() {
             chunk.append(&mut vec![format!("### {}", header), "".to_string()]);
-            for added in items.clone().iter().rev() {
-                chunk.push(get_list_bullet(added));
+            for (commit_scope, subject, hash) in items.iter().rev() {
+                chunk.push(get_list_bullet(remote, commit_scope.as_deref(), hash, subject));
             }
             chunk.push("".to_string());
         }
@@ -110,13 +154,16 @@ fn get_chunk(chunk0: &HashMap<String, Vec<String>>, scope: &str, header: &str) -
 }
 
 // check if changelog chunk already exists
-fn has_chunk(chunks: &HashMap<String, Vec<String>>, scope: &str) -> bool {
+fn has_chunk(chunks: &HashMap<String, CommitList>, scope: &str) -> bool {
     chunks.get(scope).map(|v| !v.is_empty()).unwrap_or(false)
 }
 
 // check if any changelog chunks exist
-fn any_chunks(chunks: &HashMap<String, Vec<String>>) -> bool {
-    for (scope, _) in VALID_SCOPES.iter().copied() {
+pub(crate) fn any_chunks(chunks: &HashMap<String, CommitList>, config: &Config) -> bool {
+    if has_chunk(chunks, BREAKING_SCOPE) {
+        return true;
+    }
+    for (scope, _) in config.scopes.iter() {
         if has_chunk(chunks, scope) {
             return true;
         }
@@ -124,67 +171,343 @@ fn any_chunks(chunks: &HashMap<String, Vec<String>>) -> bool {
     false
 }
 
-// create changelog from version chunks
-fn get_changelog(
-    chunks: IndexMap<String, (String, HashMap<String, Vec<String>>)>,
-    url: String,
-) -> String {
-    let mut changelog = vec!["# Changelog".to_string(), "".to_string()];
+// render releases newest-first, stopping once `skip_until` is reached so the caller
+// can splice only the releases newer than what's already on disk
+fn render_releases(
+    chunks: &IndexMap<String, (String, HashMap<String, CommitList>)>,
+    remote: &Remote,
+    config: &Config,
+    skip_until: Option<&str>,
+) -> Vec<String> {
+    let mut lines = Vec::new();
     for (i, (version, (date, chunk0))) in chunks.iter().enumerate().rev() {
+        if skip_until.is_some_and(|cutoff| version == cutoff) {
+            break;
+        }
         let version0 = if i > 0 {
             chunks.get_index(i - 1).map(|(k, _)| k.to_string())
         } else {
             None
         };
-        if any_chunks(chunk0) {
-            changelog.append(&mut get_header(version0, version, &url, date));
-            for (scope, header) in VALID_SCOPES.iter().copied() {
-                changelog.append(&mut get_chunk(chunk0, scope, header));
+        if any_chunks(chunk0, config) {
+            lines.append(&mut get_header(version0, version, remote, date));
+            lines.append(&mut get_chunk(chunk0, BREAKING_SCOPE, "Breaking Changes", remote));
+            for (scope, header) in config.scopes.iter() {
+                lines.append(&mut get_chunk(chunk0, scope, header, remote));
             }
         }
     }
+    lines
+}
+
+// find the newest version header already present in an existing CHANGELOG.md,
+// ignoring the Unreleased section since it's always regenerated
+fn find_newest_version(existing: &str) -> Option<String> {
+    existing.lines().find_map(|line| {
+        RGX_VERSION_HEADER
+            .captures(line)
+            .map(|caps| caps["version"].to_string())
+            .filter(|v| v != "Unreleased")
+    })
+}
+
+// create changelog from version chunks
+fn get_changelog(
+    chunks: IndexMap<String, (String, HashMap<String, CommitList>)>,
+    remote: &Remote,
+    config: &Config,
+) -> String {
+    let mut changelog = vec!["# Changelog".to_string(), "".to_string()];
+    changelog.append(&mut render_releases(&chunks, remote, config, None));
     changelog.join("\n").trim_end().to_string()
 }
 
-fn main() {
-    let url = git_remote_url();
-    let log = git_log();
+// find the byte offset of the first existing release header (a `## [...]` line), so
+// only the content before it (title plus any hand-written preamble) is left in place
+fn first_release_offset(existing: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in existing.split_inclusive('\n') {
+        if RGX_VERSION_HEADER.is_match(line.trim_end_matches('\n')) {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// find the byte offset just after the title line and its trailing blank line, used
+// when `existing` has no release header yet so a hand-written preamble below the
+// title (e.g. a "Keep a Changelog" intro paragraph) is still preserved
+fn first_body_offset(existing: &str) -> usize {
+    let mut lines = existing.split_inclusive('\n');
+    let Some(title) = lines.next() else {
+        return 0;
+    };
+    if !title.trim_end_matches('\n').starts_with("# ") {
+        return 0;
+    }
+    let mut offset = title.len();
+    if let Some(blank) = lines.next() {
+        if blank.trim_end_matches('\n').is_empty() {
+            offset += blank.len();
+        }
+    }
+    offset
+}
+
+// regenerate only the releases newer than what's already in `existing`, splicing
+// them in just above the first existing release header and leaving everything
+// before it (title, and any preamble the user wrote) untouched
+fn prepend_changelog(
+    chunks: &IndexMap<String, (String, HashMap<String, CommitList>)>,
+    remote: &Remote,
+    config: &Config,
+    existing: &str,
+) -> String {
+    let cutoff = find_newest_version(existing);
+    let new_releases = render_releases(chunks, remote, config, cutoff.as_deref())
+        .join("\n")
+        .trim_end()
+        .to_string();
+
+    if new_releases.is_empty() {
+        return existing.trim_end().to_string();
+    }
+
+    let offset = first_release_offset(existing).unwrap_or_else(|| first_body_offset(existing));
+    let (prefix, rest) = existing.split_at(offset);
+    let prefix = if prefix.trim().is_empty() { "# Changelog\n\n" } else { prefix };
+    // drop the existing Unreleased section, which the freshly rendered one replaces
+    let rest = if rest.starts_with("## [Unreleased]") {
+        let after = &rest[rest.find('\n').map_or(rest.len(), |i| i + 1)..];
+        first_release_offset(after).map_or("", |i| &after[i..])
+    } else {
+        rest
+    };
+    format!("{}{}\n\n{}", prefix, new_releases, rest).trim_end().to_string()
+}
 
-    let mut chunks: IndexMap<String, (String, HashMap<String, Vec<String>>)> = IndexMap::new();
-    let mut chunk0: HashMap<String, Vec<String>> = HashMap::new();
+// parse git_log's records into version chunks, keyed oldest-to-newest with the
+// Unreleased commits under "main"
+fn build_chunks(log: &[String], config: &Config) -> IndexMap<String, (String, HashMap<String, CommitList>)> {
+    let mut chunks: IndexMap<String, (String, HashMap<String, CommitList>)> = IndexMap::new();
+    let mut chunk0: HashMap<String, CommitList> = HashMap::new();
     let mut date = String::new();
 
-    for line in log.iter().rev() {
-        let caps_line = RGX_GIT.captures(line);
+    for record in log.iter().rev() {
+        let mut fields = record.splitn(5, FIELD_SEP);
+        let date_next = fields.next().unwrap_or_default();
+        let refs = fields.next().unwrap_or_default();
+        let subject = fields.next().unwrap_or_default();
+        let hash = fields.next().unwrap_or_default();
+        let body = fields.next().unwrap_or_default();
+
         // use most recent date for changelog sections
-        if let Some(date_next) = get_match(&caps_line, "date") {
-            date = date_next;
+        if !date_next.is_empty() {
+            date = date_next.to_string();
         }
+
         // add commit to section chunk map
-        if let (Some(scope), Some(commit)) = (
-            get_match(&caps_line, "scope"),
-            get_match(&caps_line, "commit"),
+        let caps_subject = RGX_GIT.captures(subject);
+        if let (Some(commit_type), Some(commit)) = (
+            get_match(&caps_subject, "type"),
+            get_match(&caps_subject, "commit"),
         ) {
-            chunk0.entry(scope).or_default().push(commit);
+            let scope = get_match(&caps_subject, "scope");
+            let is_breaking = caps_subject
+                .as_ref()
+                .and_then(|c| c.name("breaking"))
+                .is_some()
+                || RGX_BREAKING.is_match(body);
+
+            // a breaking commit is listed only under "Breaking Changes", not also
+            // under its own type's section
+            if is_breaking {
+                chunk0
+                    .entry(BREAKING_SCOPE.to_string())
+                    .or_default()
+                    .push((scope, commit, hash.to_string()));
+            } else if !config.ignore.iter().any(|s| s == &commit_type) {
+                chunk0
+                    .entry(commit_type)
+                    .or_default()
+                    .push((scope, commit, hash.to_string()));
+            }
         }
+
         // add all scope-specific commits when there is a valid version
-        if let Some(refs) = get_match(&caps_line, "refs") {
-            let caps_tag = RGX_REF.captures(&refs);
-            if let Some(version) = get_match(&caps_tag, "version") {
-                // if version 1.0.0 has no entry, add a default one
-                if (version == "v1.0.0" || version == "1.0.0") && !any_chunks(&chunk0) {
-                    chunk0
-                        .entry("feat".to_string())
-                        .or_default()
-                        .push("initial release".to_string());
-                }
-                chunks.insert(version.clone(), (date.clone(), chunk0.clone()));
-                chunk0.clear();
+        let caps_tag = RGX_REF.captures(refs);
+        if let Some(version) = get_match(&caps_tag, "version") {
+            // if version 1.0.0 has no entry, add a default one
+            if (version == "v1.0.0" || version == "1.0.0") && !any_chunks(&chunk0, config) {
+                chunk0
+                    .entry("feat".to_string())
+                    .or_default()
+                    .push((None, "initial release".to_string(), String::new()));
             }
+            chunks.insert(version.clone(), (date.clone(), chunk0.clone()));
+            chunk0.clear();
         }
     }
     chunks.insert(String::from("main"), (date, chunk0));
+    chunks
+}
+
+fn main() {
+    let config = Config::load();
+    let args = Args::parse();
+
+    let (remote, mut chunks) = if let Some(path) = &args.from_context {
+        let text =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let ctx: context::Context =
+            serde_json::from_str(&text).unwrap_or_else(|e| panic!("invalid context: {}", e));
+        let remote = Remote::from_resolved(ctx.remote_url, &ctx.provider);
+        (remote, ctx.chunks)
+    } else {
+        let remote = Remote::new(&git_remote_url(), config.provider.as_deref());
+        let chunks = build_chunks(&git_log(), &config);
+        (remote, chunks)
+    };
+
+    if args.context {
+        let ctx = context::Context {
+            remote_url: remote.url().to_string(),
+            provider: remote.provider_name().to_string(),
+            chunks,
+        };
+        println!("{}", serde_json::to_string_pretty(&ctx).unwrap());
+        return;
+    }
+
+    if args.should_bump() {
+        let previous_version = if chunks.len() > 1 {
+            chunks.get_index(chunks.len() - 2).map(|(k, _)| k.clone())
+        } else {
+            None
+        };
+        let (_, unreleased) = chunks.get_index(chunks.len() - 1).unwrap();
+        let bump = version::classify_bump(&unreleased.1);
+        let next_version = version::next_version(previous_version.as_deref(), bump);
+
+        if args.print_version {
+            println!("{}", next_version.unwrap_or_default());
+            return;
+        }
+        if let Some(next_version) = next_version {
+            let (_, value) = chunks.shift_remove_entry("main").unwrap();
+            chunks.insert(next_version, value);
+        }
+    }
+
+    let output = if args.prepend {
+        let existing = fs::read_to_string("CHANGELOG.md").unwrap_or_default();
+        prepend_changelog(&chunks, &remote, &config, &existing)
+    } else if args.any_template() {
+        let releases = template::build_releases(&chunks, &remote, &config);
+        let read_template = |path: &Option<std::path::PathBuf>| {
+            path.as_ref()
+                .map(|p| fs::read_to_string(p).unwrap_or_else(|e| panic!("{}: {}", p.display(), e)))
+        };
+        let header = read_template(&args.template_header);
+        let body = read_template(&args.template_body);
+        let footer = read_template(&args.template_footer);
+        template::render(&releases, header.as_deref(), body.as_deref(), footer.as_deref())
+    } else {
+        get_changelog(chunks, &remote, &config)
+    };
 
     let mut file = File::create("CHANGELOG.md").unwrap();
-    writeln!(file, "{}", get_changelog(chunks, url)).unwrap();
+    writeln!(file, "{}", output).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote() -> Remote {
+        Remote::new("https://github.com/owner/repo.git", None)
+    }
+
+    fn chunks_with_unreleased(commits: Vec<(&str, Option<&str>, &str)>) -> IndexMap<String, (String, HashMap<String, CommitList>)> {
+        let mut chunk0: HashMap<String, CommitList> = HashMap::new();
+        for (section, scope, subject) in commits {
+            chunk0
+                .entry(section.to_string())
+                .or_default()
+                .push((scope.map(str::to_string), subject.to_string(), String::new()));
+        }
+        let mut chunks = IndexMap::new();
+        chunks.insert("main".to_string(), ("2026-01-01".to_string(), chunk0));
+        chunks
+    }
+
+    #[test]
+    fn first_release_offset_finds_first_header() {
+        let existing = "# Changelog\n\n## [1.0.0](url) - 2026-01-01\n\n* entry\n";
+        assert_eq!(first_release_offset(existing), Some("# Changelog\n\n".len()));
+    }
+
+    #[test]
+    fn first_release_offset_none_without_a_header() {
+        let existing = "# Changelog\n\nAll notable changes are documented here.\n";
+        assert_eq!(first_release_offset(existing), None);
+    }
+
+    #[test]
+    fn first_body_offset_skips_title_and_blank_line() {
+        let existing = "# Changelog\n\nAll notable changes are documented here.\n";
+        let offset = first_body_offset(existing);
+        assert_eq!(&existing[offset..], "All notable changes are documented here.\n");
+    }
+
+    #[test]
+    fn prepend_changelog_preserves_preamble_with_no_release_header_yet() {
+        let existing = "# Changelog\n\nAll notable changes to this project will be documented here.\nFormat based on Keep a Changelog.\n";
+        let chunks = chunks_with_unreleased(vec![("feat", None, "add widget")]);
+        let config = Config::default();
+        let output = prepend_changelog(&chunks, &remote(), &config, existing);
+
+        assert!(output.contains("add widget"));
+        assert!(output.contains("All notable changes to this project will be documented here."));
+        assert!(output.contains("Format based on Keep a Changelog."));
+        assert!(output.find("## [Unreleased]").unwrap() < output.find("All notable changes").unwrap());
+    }
+
+    #[test]
+    fn prepend_changelog_splices_above_first_release_header() {
+        let existing =
+            "# Changelog\n\nHand-written intro.\n\n## [1.0.0](url) - 2026-01-01\n\n* old entry\n";
+        let chunks = chunks_with_unreleased(vec![("feat", None, "add widget")]);
+        let config = Config::default();
+        let output = prepend_changelog(&chunks, &remote(), &config, existing);
+
+        assert!(output.find("Hand-written intro.").unwrap() < output.find("## [Unreleased]").unwrap());
+        assert!(output.find("## [Unreleased]").unwrap() < output.find("## [1.0.0]").unwrap());
+        assert!(output.contains("old entry"));
+    }
+
+    #[test]
+    fn build_chunks_lists_a_breaking_commit_only_once() {
+        let record = format!(
+            "2026-01-01{sep}{sep}feat(ui)!: redesign button{sep}abc123{sep}",
+            sep = FIELD_SEP
+        );
+        let config = Config::default();
+        let chunks = build_chunks(&[record], &config);
+        let (_, chunk0) = chunks.get("main").unwrap();
+
+        assert_eq!(chunk0.get(BREAKING_SCOPE).map(Vec::len), Some(1));
+        assert!(chunk0.get("feat").map(Vec::is_empty).unwrap_or(true));
+    }
+
+    #[test]
+    fn prepend_changelog_no_op_when_nothing_new() {
+        let existing = "# Changelog\n\n## [1.0.0](url) - 2026-01-01\n\n* old entry\n";
+        let chunks: IndexMap<String, (String, HashMap<String, CommitList>)> = IndexMap::new();
+        let config = Config::default();
+        let output = prepend_changelog(&chunks, &remote(), &config, existing);
+
+        assert_eq!(output, existing.trim_end());
+    }
 }
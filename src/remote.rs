@@ -0,0 +1,226 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+// regex to match `#123`-style issue/PR references in a commit subject
+static RGX_ISSUE: Lazy<Regex> = Lazy::new(|| Regex::new(r"#(?P<num>\d+)").unwrap());
+
+// forges whose compare/release/commit/issue url shapes we know how to build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Provider {
+    GitHub,
+    GitLab,
+    Bitbucket,
+}
+
+impl Provider {
+    // sniff the provider from the origin's host
+    fn from_host(host: &str) -> Provider {
+        if host.contains("gitlab") {
+            Provider::GitLab
+        } else if host.contains("bitbucket") {
+            Provider::Bitbucket
+        } else {
+            Provider::GitHub
+        }
+    }
+
+    // parse a provider named explicitly in config or a serialized context, overriding
+    // host sniffing
+    fn from_name(name: &str) -> Option<Provider> {
+        match name.to_lowercase().as_str() {
+            "github" => Some(Provider::GitHub),
+            "gitlab" => Some(Provider::GitLab),
+            "bitbucket" => Some(Provider::Bitbucket),
+            _ => None,
+        }
+    }
+
+    // the name used to serialize this provider into a context document
+    fn name(self) -> &'static str {
+        match self {
+            Provider::GitHub => "github",
+            Provider::GitLab => "gitlab",
+            Provider::Bitbucket => "bitbucket",
+        }
+    }
+}
+
+// a normalized remote origin plus the url shapes of its forge
+pub struct Remote {
+    provider: Provider,
+    base_url: String,
+}
+
+impl Remote {
+    // build a `Remote` from a git `origin` url (HTTPS or SSH), optionally overriding
+    // the sniffed provider with one named in config
+    pub fn new(origin: &str, provider_override: Option<&str>) -> Remote {
+        let base_url = normalize_origin(origin);
+        let host = base_url
+            .strip_prefix("https://")
+            .or_else(|| base_url.strip_prefix("http://"))
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or("");
+        let provider = provider_override
+            .and_then(Provider::from_name)
+            .unwrap_or_else(|| Provider::from_host(host));
+        Remote { provider, base_url }
+    }
+
+    // rebuild a `Remote` from a previously-resolved base url and provider name, e.g.
+    // when loading a `--context` document. Used instead of `new` so a context captured
+    // with a provider override reproduces identical output without needing that same
+    // override to be present wherever it's rendered.
+    pub fn from_resolved(base_url: String, provider_name: &str) -> Remote {
+        let provider = Provider::from_name(provider_name).unwrap_or(Provider::GitHub);
+        Remote { provider, base_url }
+    }
+
+    pub fn compare_url(&self, from: &str, to: &str) -> String {
+        match self.provider {
+            Provider::GitHub => format!("{}/compare/{}...{}", self.base_url, from, to),
+            Provider::GitLab => format!("{}/-/compare/{}...{}", self.base_url, from, to),
+            Provider::Bitbucket => format!("{}/branches/compare/{}..{}", self.base_url, to, from),
+        }
+    }
+
+    pub fn release_url(&self, version: &str) -> String {
+        match self.provider {
+            Provider::GitHub => format!("{}/releases/tag/{}", self.base_url, version),
+            Provider::GitLab => format!("{}/-/tags/{}", self.base_url, version),
+            Provider::Bitbucket => format!("{}/commits/tag/{}", self.base_url, version),
+        }
+    }
+
+    pub fn commit_url(&self, hash: &str) -> String {
+        match self.provider {
+            Provider::GitHub => format!("{}/commit/{}", self.base_url, hash),
+            Provider::GitLab => format!("{}/-/commit/{}", self.base_url, hash),
+            Provider::Bitbucket => format!("{}/commits/{}", self.base_url, hash),
+        }
+    }
+
+    pub fn issue_url(&self, number: &str) -> String {
+        match self.provider {
+            Provider::GitHub => format!("{}/issues/{}", self.base_url, number),
+            Provider::GitLab => format!("{}/-/issues/{}", self.base_url, number),
+            Provider::Bitbucket => format!("{}/issues/{}", self.base_url, number),
+        }
+    }
+
+    // turn `#123` references into links to the provider's issue/PR page
+    pub fn autolink(&self, text: &str) -> String {
+        RGX_ISSUE
+            .replace_all(text, |caps: &Captures| {
+                let num = &caps["num"];
+                format!("[#{}]({})", num, self.issue_url(num))
+            })
+            .to_string()
+    }
+
+    // a short markdown link to a commit, e.g. `[a1b2c3d](.../commit/a1b2c3d)`
+    pub fn commit_link(&self, hash: &str) -> String {
+        format!("[{}]({})", hash, self.commit_url(hash))
+    }
+
+    // the normalized base url, e.g. for serializing into a context document
+    pub fn url(&self) -> &str {
+        &self.base_url
+    }
+
+    // the resolved provider name, e.g. for serializing into a context document
+    pub fn provider_name(&self) -> &'static str {
+        self.provider.name()
+    }
+}
+
+// normalize an HTTPS or SSH origin into a plain https base url with no trailing `.git`
+fn normalize_origin(origin: &str) -> String {
+    let origin = origin.trim();
+    let https = if let Some(rest) = origin.strip_prefix("git@") {
+        format!("https://{}", rest.replacen(':', "/", 1))
+    } else if let Some(rest) = origin.strip_prefix("ssh://git@") {
+        format!("https://{}", rest)
+    } else {
+        origin.to_string()
+    };
+    https.strip_suffix(".git").unwrap_or(&https).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_origin_strips_https_git_suffix() {
+        assert_eq!(
+            normalize_origin("https://github.com/owner/repo.git"),
+            "https://github.com/owner/repo"
+        );
+    }
+
+    #[test]
+    fn normalize_origin_rewrites_scp_style_ssh() {
+        assert_eq!(
+            normalize_origin("git@github.com:owner/repo.git"),
+            "https://github.com/owner/repo"
+        );
+    }
+
+    #[test]
+    fn normalize_origin_rewrites_ssh_url_style() {
+        assert_eq!(
+            normalize_origin("ssh://git@gitlab.example.com/owner/repo.git"),
+            "https://gitlab.example.com/owner/repo"
+        );
+    }
+
+    #[test]
+    fn provider_sniffed_from_host() {
+        assert_eq!(Remote::new("https://github.com/o/r", None).provider_name(), "github");
+        assert_eq!(Remote::new("https://gitlab.com/o/r", None).provider_name(), "gitlab");
+        assert_eq!(
+            Remote::new("https://bitbucket.org/o/r", None).provider_name(),
+            "bitbucket"
+        );
+    }
+
+    #[test]
+    fn provider_override_beats_host_sniffing() {
+        let remote = Remote::new("https://git.example.com/o/r", Some("gitlab"));
+        assert_eq!(remote.provider_name(), "gitlab");
+    }
+
+    #[test]
+    fn from_resolved_reproduces_the_same_urls_as_new() {
+        let original = Remote::new("https://gitlab.com/o/r", None);
+        let roundtripped = Remote::from_resolved(original.url().to_string(), original.provider_name());
+        assert_eq!(roundtripped.compare_url("v1.0.0", "v1.1.0"), original.compare_url("v1.0.0", "v1.1.0"));
+        assert_eq!(roundtripped.release_url("v1.1.0"), original.release_url("v1.1.0"));
+    }
+
+    #[test]
+    fn compare_urls_differ_per_provider() {
+        assert_eq!(
+            Remote::new("https://github.com/o/r", None).compare_url("a", "b"),
+            "https://github.com/o/r/compare/a...b"
+        );
+        assert_eq!(
+            Remote::new("https://gitlab.com/o/r", None).compare_url("a", "b"),
+            "https://gitlab.com/o/r/-/compare/a...b"
+        );
+        assert_eq!(
+            Remote::new("https://bitbucket.org/o/r", None).compare_url("a", "b"),
+            "https://bitbucket.org/o/r/branches/compare/b..a"
+        );
+    }
+
+    #[test]
+    fn autolink_links_issue_references() {
+        let remote = Remote::new("https://github.com/o/r", None);
+        assert_eq!(
+            remote.autolink("fixes #42"),
+            "fixes [#42](https://github.com/o/r/issues/42)"
+        );
+    }
+}
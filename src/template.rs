@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::config::Config;
+use crate::remote::Remote;
+use crate::{any_chunks, format_item, CommitList, BREAKING_SCOPE};
+
+// default templates, chosen to reproduce the hand-built Markdown output
+const DEFAULT_HEADER: &str = "# Changelog\n\n";
+const DEFAULT_BODY: &str = include_str!("templates/body.tera");
+const DEFAULT_FOOTER: &str = "";
+
+// one changelog section, e.g. "Added" with its commit subjects
+#[derive(Serialize)]
+pub struct Group {
+    pub title: String,
+    pub items: Vec<String>,
+}
+
+// one release (or the Unreleased pseudo-release) as exposed to templates
+#[derive(Serialize)]
+pub struct Release {
+    pub version: String,
+    pub version_text: String,
+    pub date: String,
+    pub previous_version: Option<String>,
+    pub compare_url: String,
+    pub groups: Vec<Group>,
+}
+
+// build the release list consumed by the templates, newest first
+pub fn build_releases(
+    chunks: &IndexMap<String, (String, HashMap<String, CommitList>)>,
+    remote: &Remote,
+    config: &Config,
+) -> Vec<Release> {
+    let mut releases = Vec::new();
+    for (i, (version, (date, chunk0))) in chunks.iter().enumerate().rev() {
+        if !any_chunks(chunk0, config) {
+            continue;
+        }
+
+        let previous_version = if i > 0 {
+            chunks.get_index(i - 1).map(|(k, _)| k.to_string())
+        } else {
+            None
+        };
+
+        let version_text = match version.as_str() {
+            "main" => "Unreleased".to_string(),
+            _ => version.to_string(),
+        };
+
+        let compare_url = match &previous_version {
+            Some(prev) => remote.compare_url(prev, version),
+            None => remote.release_url(version),
+        };
+
+        let to_group = |title: &str, items: &CommitList| Group {
+            title: title.to_string(),
+            items: items
+                .iter()
+                .rev()
+                .map(|(scope, s, hash)| format_item(remote, scope.as_deref(), hash, s))
+                .collect(),
+        };
+
+        let mut groups: Vec<Group> = chunk0
+            .get(BREAKING_SCOPE)
+            .filter(|items| !items.is_empty())
+            .map(|items| to_group("Breaking Changes", items))
+            .into_iter()
+            .collect();
+
+        groups.extend(config.scopes.iter().filter_map(|(scope, title)| {
+            chunk0
+                .get(scope)
+                .filter(|items| !items.is_empty())
+                .map(|items| to_group(title, items))
+        }));
+
+        releases.push(Release {
+            version: version.clone(),
+            version_text,
+            date: date.clone(),
+            previous_version,
+            compare_url,
+            groups,
+        });
+    }
+    releases
+}
+
+// render the header/body/footer templates against the release context
+pub fn render(releases: &[Release], header: Option<&str>, body: Option<&str>, footer: Option<&str>) -> String {
+    let mut tera = Tera::default();
+    tera.add_raw_template("header", header.unwrap_or(DEFAULT_HEADER))
+        .expect("invalid header template");
+    tera.add_raw_template("body", body.unwrap_or(DEFAULT_BODY))
+        .expect("invalid body template");
+    tera.add_raw_template("footer", footer.unwrap_or(DEFAULT_FOOTER))
+        .expect("invalid footer template");
+
+    let mut context = Context::new();
+    context.insert("releases", releases);
+
+    let header = tera.render("header", &context).unwrap();
+    let body = tera.render("body", &context).unwrap();
+    let footer = tera.render("footer", &context).unwrap();
+
+    format!("{}{}{}", header, body, footer).trim_end().to_string()
+}
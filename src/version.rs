@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::{CommitList, BREAKING_SCOPE};
+
+// the kind of semver bump a set of unreleased commits warrants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+    None,
+}
+
+// classify the unreleased chunk into the highest bump its commits warrant
+pub fn classify_bump(chunk0: &HashMap<String, CommitList>) -> Bump {
+    let has = |scope: &str| chunk0.get(scope).map(|v| !v.is_empty()).unwrap_or(false);
+    if has(BREAKING_SCOPE) {
+        Bump::Major
+    } else if has("feat") {
+        Bump::Minor
+    } else if has("fix") {
+        Bump::Patch
+    } else {
+        Bump::None
+    }
+}
+
+// parse "v1.2.3" or "1.2.3" into (had "v" prefix, major, minor, patch)
+fn parse_version(v: &str) -> Option<(bool, u64, u64, u64)> {
+    let has_v = v.starts_with('v');
+    let mut parts = v.strip_prefix('v').unwrap_or(v).split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next()?.parse().ok()?;
+    Some((has_v, major, minor, patch))
+}
+
+// compute the next version for a bump, starting from the previous tag (or
+// v0.0.0 when there isn't one). Pre-1.0.0, a breaking change only bumps minor.
+pub fn next_version(previous: Option<&str>, bump: Bump) -> Option<String> {
+    if bump == Bump::None {
+        return None;
+    }
+
+    let (has_v, major, minor, patch) = previous
+        .and_then(parse_version)
+        .unwrap_or((true, 0, 0, 0));
+    let pre_1_0 = major == 0;
+
+    let (major, minor, patch) = match bump {
+        Bump::Major if pre_1_0 => (major, minor + 1, 0),
+        Bump::Major => (major + 1, 0, 0),
+        Bump::Minor => (major, minor + 1, 0),
+        Bump::Patch => (major, minor, patch + 1),
+        Bump::None => unreachable!(),
+    };
+
+    let prefix = if has_v { "v" } else { "" };
+    Some(format!("{}{}.{}.{}", prefix, major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(scopes: &[&str]) -> HashMap<String, CommitList> {
+        let mut chunk0: HashMap<String, CommitList> = HashMap::new();
+        for scope in scopes {
+            chunk0
+                .entry(scope.to_string())
+                .or_default()
+                .push((None, "subject".to_string(), String::new()));
+        }
+        chunk0
+    }
+
+    #[test]
+    fn classify_bump_prefers_breaking_over_feat_and_fix() {
+        let chunk0 = chunk(&[BREAKING_SCOPE, "feat", "fix"]);
+        assert_eq!(classify_bump(&chunk0), Bump::Major);
+    }
+
+    #[test]
+    fn classify_bump_prefers_feat_over_fix() {
+        let chunk0 = chunk(&["feat", "fix"]);
+        assert_eq!(classify_bump(&chunk0), Bump::Minor);
+    }
+
+    #[test]
+    fn classify_bump_falls_back_to_fix() {
+        let chunk0 = chunk(&["fix"]);
+        assert_eq!(classify_bump(&chunk0), Bump::Patch);
+    }
+
+    #[test]
+    fn classify_bump_none_when_nothing_qualifies() {
+        let chunk0 = chunk(&["docs", "chore"]);
+        assert_eq!(classify_bump(&chunk0), Bump::None);
+    }
+
+    #[test]
+    fn next_version_none_bump_returns_none() {
+        assert_eq!(next_version(Some("v1.2.3"), Bump::None), None);
+    }
+
+    #[test]
+    fn next_version_defaults_to_v0_0_0_with_no_previous() {
+        assert_eq!(next_version(None, Bump::Patch), Some("v0.0.1".to_string()));
+    }
+
+    #[test]
+    fn next_version_pre_1_0_breaking_only_bumps_minor() {
+        assert_eq!(next_version(Some("v0.3.1"), Bump::Major), Some("v0.4.0".to_string()));
+    }
+
+    #[test]
+    fn next_version_post_1_0_breaking_bumps_major_and_resets() {
+        assert_eq!(next_version(Some("v1.3.1"), Bump::Major), Some("v2.0.0".to_string()));
+    }
+
+    #[test]
+    fn next_version_minor_resets_patch() {
+        assert_eq!(next_version(Some("v1.3.4"), Bump::Minor), Some("v1.4.0".to_string()));
+    }
+
+    #[test]
+    fn next_version_patch_increments_only_patch() {
+        assert_eq!(next_version(Some("v1.3.4"), Bump::Patch), Some("v1.3.5".to_string()));
+    }
+
+    #[test]
+    fn next_version_preserves_absence_of_v_prefix() {
+        assert_eq!(next_version(Some("1.3.4"), Bump::Patch), Some("1.3.5".to_string()));
+    }
+}